@@ -1,30 +1,174 @@
 /* ~~/src/lib.rs */
 
+mod sweep;
+mod types;
+mod stable;
+
+use crate::sweep::SweepManager;
+use ldk_node::bitcoin::Network;
 use ldk_node::{Builder, Event, Node};
 use pyo3::prelude::*;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DEFAULT_ESPLORA_URL: &str = "https://blockstream.info/api/";
+
+/// Selects and configures the chain-data backend `Endur::start` connects
+/// through: Esplora (the default), Electrum, or a bitcoind JSON-RPC node.
+/// Exposed to Python so deployments can point at a private backend instead
+/// of a public Esplora instance on every `update_balances` call.
+#[pyclass]
+#[derive(Clone)]
+pub struct ChainSourceConfig {
+  #[pyo3(get, set)]
+  pub network: String,
+  #[pyo3(get, set)]
+  pub esplora_url: Option<String>,
+  #[pyo3(get, set)]
+  pub electrum_url: Option<String>,
+  #[pyo3(get, set)]
+  pub bitcoind_rpc_host: Option<String>,
+  #[pyo3(get, set)]
+  pub bitcoind_rpc_port: Option<u16>,
+  #[pyo3(get, set)]
+  pub bitcoind_rpc_user: Option<String>,
+  #[pyo3(get, set)]
+  pub bitcoind_rpc_password: Option<String>,
+}
+
+#[pymethods]
+impl ChainSourceConfig {
+  #[new]
+  #[pyo3(signature = (
+    network="bitcoin".to_string(),
+    esplora_url=None,
+    electrum_url=None,
+    bitcoind_rpc_host=None,
+    bitcoind_rpc_port=None,
+    bitcoind_rpc_user=None,
+    bitcoind_rpc_password=None,
+  ))]
+  fn new(
+    network: String,
+    esplora_url: Option<String>,
+    electrum_url: Option<String>,
+    bitcoind_rpc_host: Option<String>,
+    bitcoind_rpc_port: Option<u16>,
+    bitcoind_rpc_user: Option<String>,
+    bitcoind_rpc_password: Option<String>,
+  ) -> Self {
+    Self {
+      network,
+      esplora_url,
+      electrum_url,
+      bitcoind_rpc_host,
+      bitcoind_rpc_port,
+      bitcoind_rpc_user,
+      bitcoind_rpc_password,
+    }
+  }
+}
+
+fn parse_network(network: &str) -> PyResult<Network> {
+  network
+    .parse::<Network>()
+    .map_err(|_| pyo3::exceptions::PyValueError::new_err(format!("Unknown network: {}", network)))
+}
+
+/// Rejects a non-mainnet network when the chain source is the mainnet-only
+/// default Esplora URL, whether given explicitly or used as the fallback.
+/// Explicitly supplied Esplora/Electrum URLs and bitcoind RPC targets are
+/// not checked against the network.
+fn validate_chain_source_network(network: Network, config: &ChainSourceConfig) -> PyResult<()> {
+  let falls_back_to_esplora = config.electrum_url.is_none() && config.bitcoind_rpc_host.is_none();
+  let uses_default_esplora = falls_back_to_esplora
+    && config.esplora_url.as_deref().unwrap_or(DEFAULT_ESPLORA_URL) == DEFAULT_ESPLORA_URL;
+
+  if network != Network::Bitcoin && uses_default_esplora {
+    return Err(pyo3::exceptions::PyValueError::new_err(format!(
+      "network {:?} requires an explicit esplora_url, electrum_url, or bitcoind RPC target; \
+       the default Esplora endpoint only serves mainnet",
+      network
+    )));
+  }
+
+  Ok(())
+}
 
 #[pyclass]
 pub struct Endur {
   node: Option<Arc<Node>>,
+  sweep: Option<Arc<SweepManager>>,
+  rgs_synced_at: Option<u64>,
+  // Set when `start` was given an `rgs_snapshot_url` and cleared once
+  // `graph_sync_status` observes the graph has actually been populated.
+  // RGS sync runs in the background, so we can't stamp `rgs_synced_at`
+  // synchronously in `start` — this tracks that a stamp is still owed.
+  rgs_sync_pending: bool,
 }
 
 #[pymethods]
 impl Endur {
   #[new]
   fn new() -> Self {
-    Self { node: None }
+    Self {
+      node: None,
+      sweep: None,
+      rgs_synced_at: None,
+      rgs_sync_pending: false,
+    }
   }
 
-  fn start(&mut self, data_dir: Option<String>) -> PyResult<String> {
+  #[pyo3(signature = (data_dir=None, chain_source=None, rgs_snapshot_url=None))]
+  fn start(
+    &mut self,
+    data_dir: Option<String>,
+    chain_source: Option<ChainSourceConfig>,
+    rgs_snapshot_url: Option<String>,
+  ) -> PyResult<String> {
+    let chain_source = chain_source.unwrap_or(ChainSourceConfig {
+      network: "bitcoin".to_string(),
+      esplora_url: None,
+      electrum_url: None,
+      bitcoind_rpc_host: None,
+      bitcoind_rpc_port: None,
+      bitcoind_rpc_user: None,
+      bitcoind_rpc_password: None,
+    });
+
+    let network = parse_network(&chain_source.network)?;
+    validate_chain_source_network(network, &chain_source)?;
+
     let mut builder = Builder::new();
+    builder.set_network(network);
 
-    // Basic configuration
-    builder.set_network(ldk_node::bitcoin::Network::Bitcoin);
-    builder.set_chain_source_esplora("https://blockstream.info/api/".to_string(), None);
+    if let Some(electrum_url) = &chain_source.electrum_url {
+      builder.set_chain_source_electrum(electrum_url.clone(), None);
+    } else if let Some(host) = &chain_source.bitcoind_rpc_host {
+      let port = chain_source.bitcoind_rpc_port.ok_or_else(|| {
+        pyo3::exceptions::PyValueError::new_err("bitcoind_rpc_port is required with bitcoind_rpc_host")
+      })?;
+      let user = chain_source.bitcoind_rpc_user.clone().ok_or_else(|| {
+        pyo3::exceptions::PyValueError::new_err("bitcoind_rpc_user is required with bitcoind_rpc_host")
+      })?;
+      let password = chain_source.bitcoind_rpc_password.clone().ok_or_else(|| {
+        pyo3::exceptions::PyValueError::new_err("bitcoind_rpc_password is required with bitcoind_rpc_host")
+      })?;
+      builder.set_chain_source_bitcoind_rpc(host.clone(), port, user, password);
+    } else {
+      let esplora_url = chain_source
+        .esplora_url
+        .clone()
+        .unwrap_or_else(|| DEFAULT_ESPLORA_URL.to_string());
+      builder.set_chain_source_esplora(esplora_url, None);
+    }
 
-    if let Some(dir) = data_dir {
-      builder.set_storage_dir_path(dir);
+    if let Some(dir) = &data_dir {
+      builder.set_storage_dir_path(dir.clone());
+    }
+
+    if let Some(url) = &rgs_snapshot_url {
+      builder.set_gossip_source_rgs(url.clone());
     }
 
     let node = Arc::new(
@@ -38,6 +182,17 @@ impl Endur {
       .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(format!("Start failed: {}", e)))?;
 
     let node_id = node.node_id().to_string();
+    self.sweep = Some(Arc::new(SweepManager::new(
+      data_dir.as_deref().unwrap_or("."),
+    )));
+    // RGS sync runs in the background during start(), so its completion
+    // isn't signaled synchronously here; `graph_sync_status` stamps
+    // `rgs_synced_at` lazily, the first time it observes the graph has
+    // actually been populated, rather than checking once at this instant
+    // (which would almost always see an empty graph regardless of whether
+    // the sync eventually succeeds).
+    self.rgs_synced_at = None;
+    self.rgs_sync_pending = rgs_snapshot_url.is_some();
     self.node = Some(node);
 
     Ok(node_id)
@@ -91,6 +246,49 @@ impl Endur {
     }
   }
 
+  /// Builds a reusable BOLT12 offer for `amount_sats`. Unlike a BOLT11
+  /// invoice, the same offer can be paid multiple times, which lets
+  /// `check_stability` rebalance against a standing destination instead of
+  /// minting a fresh invoice per payment.
+  fn generate_offer(&self, amount_sats: u64, description: &str) -> PyResult<String> {
+    match &self.node {
+      Some(node) => {
+        let msats = amount_sats * 1000;
+        let offer = node
+          .bolt12_payment()
+          .receive(msats, description, None, None)
+          .map_err(|e| {
+            pyo3::exceptions::PyRuntimeError::new_err(format!("Offer generation failed: {}", e))
+          })?;
+        Ok(offer.to_string())
+      }
+      None => Err(pyo3::exceptions::PyRuntimeError::new_err(
+        "Node not started",
+      )),
+    }
+  }
+
+  #[pyo3(signature = (offer_str, amount_msat=None))]
+  fn pay_offer(&self, offer_str: &str, amount_msat: Option<u64>) -> PyResult<String> {
+    match &self.node {
+      Some(node) => {
+        let offer = offer_str
+          .parse::<ldk_node::lightning::offers::offer::Offer>()
+          .map_err(|_| pyo3::exceptions::PyValueError::new_err("Invalid BOLT12 offer"))?;
+        let payment_id = node
+          .bolt12_payment()
+          .send(&offer, amount_msat, None)
+          .map_err(|e| {
+            pyo3::exceptions::PyRuntimeError::new_err(format!("Offer payment failed: {}", e))
+          })?;
+        Ok(payment_id.to_string())
+      }
+      None => Err(pyo3::exceptions::PyRuntimeError::new_err(
+        "Node not started",
+      )),
+    }
+  }
+
   fn get_new_address(&self) -> PyResult<String> {
     match &self.node {
       Some(node) => {
@@ -120,6 +318,38 @@ impl Endur {
     }
   }
 
+  /// Reports how seeded the routing graph is: the unix timestamp the RGS
+  /// snapshot was applied at (`None` if no `rgs_snapshot_url` was given to
+  /// `start`, or if it was given but the sync hasn't populated the graph
+  /// yet), and the current node/channel counts in the network graph. Since
+  /// RGS sync completes in the background, the timestamp is stamped here,
+  /// lazily, the first time the graph is observed to be non-empty.
+  fn graph_sync_status(&mut self) -> PyResult<(Option<u64>, usize, usize)> {
+    match &self.node {
+      Some(node) => {
+        let graph = node.network_graph();
+        let readonly_graph = graph.read_only();
+        let node_count = readonly_graph.nodes().len();
+        let channel_count = readonly_graph.channels().len();
+
+        if self.rgs_sync_pending && channel_count > 0 {
+          self.rgs_synced_at = Some(
+            SystemTime::now()
+              .duration_since(UNIX_EPOCH)
+              .unwrap_or_default()
+              .as_secs(),
+          );
+          self.rgs_sync_pending = false;
+        }
+
+        Ok((self.rgs_synced_at, node_count, channel_count))
+      }
+      None => Err(pyo3::exceptions::PyRuntimeError::new_err(
+        "Node not started",
+      )),
+    }
+  }
+
   fn process_events(&self) -> PyResult<Vec<String>> {
     match &self.node {
       Some(node) => {
@@ -132,9 +362,48 @@ impl Endur {
             Event::PaymentReceived { amount_msat, .. } => {
               format!("Payment received: {} msats", amount_msat)
             }
-            Event::PaymentSuccessful { payment_hash, .. } => {
+            Event::PaymentSuccessful {
+              payment_id,
+              payment_hash,
+              ..
+            } => {
+              if let Some(id) = payment_id {
+                crate::stable::record_payment_outcome(id, crate::stable::PaymentOutcomeSignal::Successful);
+              }
               format!("Payment successful: {}", payment_hash)
             }
+            Event::PaymentFailed {
+              payment_id,
+              payment_hash,
+              reason,
+            } => {
+              if let Some(id) = payment_id {
+                let class = reason
+                  .map(crate::stable::classify_failure_reason)
+                  .unwrap_or(crate::stable::PaymentFailureClass::Other);
+                crate::stable::record_payment_outcome(id, crate::stable::PaymentOutcomeSignal::Failed(class));
+              }
+              format!("Payment failed: {:?}", payment_hash)
+            }
+            Event::SpendableOutputs { outputs, .. } => {
+              let count = outputs.len();
+              if let Some(sweep) = &self.sweep {
+                sweep.queue(outputs);
+                let pending_count = sweep.pending_count();
+                match sweep.sweep_now(node) {
+                  Ok((txid, swept_sats)) => format!(
+                    "Swept {} spendable output(s) ({} sats) in {}",
+                    pending_count, swept_sats, txid
+                  ),
+                  Err(e) => format!(
+                    "Queued {} spendable output(s) but sweep failed: {}",
+                    count, e
+                  ),
+                }
+              } else {
+                format!("Spendable outputs not queued, sweep manager not initialized: {}", count)
+              }
+            }
             _ => format!("Other event: {:?}", event),
           };
           events.push(event_str);
@@ -147,11 +416,35 @@ impl Endur {
       )),
     }
   }
+
+  fn list_pending_sweeps(&self) -> PyResult<usize> {
+    match &self.sweep {
+      Some(sweep) => Ok(sweep.pending_count()),
+      None => Err(pyo3::exceptions::PyRuntimeError::new_err(
+        "Node not started",
+      )),
+    }
+  }
+
+  /// Sweeps every pending spendable output to a fresh on-chain address now,
+  /// instead of waiting for the next `SpendableOutputs` event. Returns the
+  /// broadcast txid and the total amount swept, in sats.
+  fn sweep_now(&self) -> PyResult<(String, u64)> {
+    match (&self.node, &self.sweep) {
+      (Some(node), Some(sweep)) => sweep
+        .sweep_now(node)
+        .map_err(pyo3::exceptions::PyRuntimeError::new_err),
+      _ => Err(pyo3::exceptions::PyRuntimeError::new_err(
+        "Node not started",
+      )),
+    }
+  }
 }
 
 #[pymodule]
 fn endur(m: &Bound<'_, PyModule>) -> PyResult<()> {
   m.add_class::<Endur>()?;
+  m.add_class::<ChainSourceConfig>()?;
   Ok(())
 }
 
@@ -3,10 +3,29 @@
 use crate::audit::audit_event;
 use crate::oracles::get_cached_price;
 use crate::types::{Bitcoin, StableChannel, USD};
+use ldk_node::lightning::events::PaymentFailureReason;
+use ldk_node::payment::PaymentId;
 use ldk_node::{lightning::ln::types::ChannelId, Node};
 use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
 use ureq::Agent;
 
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const PAR_THRESHOLD_PERCENT: f64 = 0.1;
+const DUST_LIMIT_SATS: u64 = 546;
+const INITIAL_RETRY_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(4);
+const PAYMENT_OUTCOME_TIMEOUT: Duration = Duration::from_secs(8);
+const PAYMENT_OUTCOME_POLL_INTERVAL: Duration = Duration::from_millis(200);
+// Caps the *entire* retry loop's blocking time, not just a single attempt's
+// wait. `check_stability` runs synchronously, so whatever calls into it
+// from Python must wrap the call in `py.allow_threads(...)` to avoid
+// freezing the interpreter for the duration of this budget.
+const STABILITY_PAYMENT_MAX_TOTAL_WAIT: Duration = Duration::from_secs(20);
+
 /// Get the current BTC/USD price, preferring cached value when available
 pub fn get_current_price(agent: &Agent) -> f64 {
   // First try the cached price
@@ -88,6 +107,11 @@ pub fn update_balances<'update_balance_lifetime>(
   (true, stable_channel)
 }
 
+/// Runs one stability poll, rebalancing via `send_stability_payment` if
+/// needed. This blocks synchronously for up to
+/// `STABILITY_PAYMENT_MAX_TOTAL_WAIT` while retrying a payment, so any
+/// pyo3 entry point calling this must wrap it in `py.allow_threads(...)` —
+/// otherwise the GIL stays held for the whole retry loop.
 pub fn check_stability(node: &Node, stable_channel: &mut StableChannel, price: f64) {
   let current_price = if price > 0.0 {
     price
@@ -151,28 +175,303 @@ pub fn check_stability(node: &Node, stable_channel: &mut StableChannel, price: f
     return;
   }
 
-  let amt = USD::to_msats(dollars_from_par, stable_channel.latest_price);
-  match node.spontaneous_payment().send(amt, stable_channel.counterparty, None) {
-    Ok(payment_id) => {
-      stable_channel.payment_made = true;
+  if !stable_channel.initial_graph_check_passed {
+    // Latch regardless of outcome: this check only exists to skip the very
+    // first PAY attempt while gossip is still trickling in after RGS sync.
+    // Small/young networks (testnet, signet, regtest) can legitimately stay
+    // well under MIN_GRAPH_CHANNELS forever, so re-checking on every poll
+    // would block them from ever paying instead of just deferring the first
+    // attempt.
+    stable_channel.initial_graph_check_passed = true;
+
+    if !graph_has_min_readiness(node) {
       audit_event(
-        "STABILITY_PAYMENT_SENT",
+        "STABILITY_PAY_SKIPPED_GRAPH_NOT_READY",
         json!({
-            "amount_msats": amt,
-            "payment_id": payment_id.to_string(),
-            "counterparty": stable_channel.counterparty.to_string()
+            "channel_count": node.network_graph().read_only().channels().len(),
+            "min_required": MIN_GRAPH_CHANNELS
         }),
       );
+      return;
+    }
+  }
+
+  send_stability_payment(node, stable_channel, dollars_from_par);
+}
+
+/// Minimum number of channels the network graph must have before the very
+/// first `PAY` attempt is allowed; this is a one-time deferral, not a
+/// standing floor — `check_stability` latches `initial_graph_check_passed`
+/// after this check runs once, regardless of outcome, since small/young
+/// networks (testnet, signet, regtest) can legitimately stay well under
+/// this count forever. A freshly-started node with no rapid gossip sync
+/// has an empty graph, so routing fails every time until gossip trickles
+/// in; deferring the first attempt avoids burning retry budget on a check
+/// that can't possibly find a route.
+const MIN_GRAPH_CHANNELS: usize = 50;
+
+fn graph_has_min_readiness(node: &Node) -> bool {
+  node.network_graph().read_only().channels().len() >= MIN_GRAPH_CHANNELS
+}
+
+/// Classification of why a stability payment attempt failed, derived from
+/// LDK's `PaymentFailureReason`. Only `RouteNotFound` is retried — a
+/// transient liquidity problem that may clear up on its own; the others
+/// mean retrying the same payment won't help.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PaymentFailureClass {
+  RouteNotFound,
+  RecipientRejected,
+  PaymentExpired,
+  Other,
+}
+
+pub(crate) fn classify_failure_reason(reason: PaymentFailureReason) -> PaymentFailureClass {
+  match reason {
+    PaymentFailureReason::RouteNotFound => PaymentFailureClass::RouteNotFound,
+    PaymentFailureReason::RecipientRejected => PaymentFailureClass::RecipientRejected,
+    PaymentFailureReason::PaymentExpired => PaymentFailureClass::PaymentExpired,
+    _ => PaymentFailureClass::Other,
+  }
+}
+
+/// Resolution of a dispatched payment, as reported by the
+/// `PaymentSuccessful` / `PaymentFailed` events — `send()` itself only
+/// reports whether the payment was *dispatched*, not how LDK's routing
+/// attempt turned out.
+#[derive(Clone, Copy)]
+pub(crate) enum PaymentOutcomeSignal {
+  Successful,
+  Failed(PaymentFailureClass),
+}
+
+enum PaymentOutcome {
+  Successful,
+  Failed(PaymentFailureClass),
+  TimedOut,
+}
+
+/// `process_events` in `lib.rs` is the sole consumer of the node's event
+/// queue — `next_event()`/`event_handled()` must only ever be called from
+/// there, since a second drain would race it and could silently discard
+/// events (e.g. `SpendableOutputs`) that `process_events` owns. This
+/// registry lets `process_events` hand a resolved payment outcome to
+/// whichever stability check is waiting on it without either side touching
+/// the other's events.
+static PAYMENT_OUTCOMES: OnceLock<Mutex<HashMap<PaymentId, PaymentOutcomeSignal>>> = OnceLock::new();
+
+fn payment_outcomes() -> &'static Mutex<HashMap<PaymentId, PaymentOutcomeSignal>> {
+  PAYMENT_OUTCOMES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Called by `process_events` when it sees a `PaymentSuccessful` or
+/// `PaymentFailed` event, so `await_payment_outcome` can resolve it.
+pub(crate) fn record_payment_outcome(payment_id: PaymentId, outcome: PaymentOutcomeSignal) {
+  payment_outcomes().lock().unwrap().insert(payment_id, outcome);
+}
+
+fn take_payment_outcome(payment_id: PaymentId) -> Option<PaymentOutcomeSignal> {
+  payment_outcomes().lock().unwrap().remove(&payment_id)
+}
+
+/// Polls the outcome registry (populated by `process_events`) until
+/// `deadline`, rather than draining the node's event queue itself.
+fn await_payment_outcome(payment_id: PaymentId, deadline: Instant) -> PaymentOutcome {
+  while Instant::now() < deadline {
+    match take_payment_outcome(payment_id) {
+      Some(PaymentOutcomeSignal::Successful) => return PaymentOutcome::Successful,
+      Some(PaymentOutcomeSignal::Failed(class)) => return PaymentOutcome::Failed(class),
+      None => thread::sleep(PAYMENT_OUTCOME_POLL_INTERVAL.min(deadline.saturating_duration_since(Instant::now()))),
     }
-    Err(e) => {
+  }
+
+  payment_outcomes().lock().unwrap().remove(&payment_id);
+  PaymentOutcome::TimedOut
+}
+
+/// Attempts the `PAY` rebalance up to `stable_channel.max_retries` times in
+/// total (default 3) with exponential backoff between attempts. The first
+/// attempt uses the delta `check_stability` just computed; balances and the
+/// peg delta are refreshed from a freshly-fetched price before each retry,
+/// since BTC moving during retries can bring the channel back within the
+/// 0.1% par threshold or push the remaining delta under the dust limit, in
+/// which case the attempt is abandoned rather than forced through. Only
+/// route-level failures count against the retry budget; a rejection or
+/// expiry from the peer is terminal and abandoned immediately.
+fn send_stability_payment(node: &Node, stable_channel: &mut StableChannel, initial_dollars_from_par: f64) {
+  let max_retries = if stable_channel.max_retries > 0 {
+    stable_channel.max_retries
+  } else {
+    DEFAULT_MAX_RETRIES
+  };
+
+  let agent = Agent::new();
+  let mut dollars_from_par = initial_dollars_from_par;
+  let mut backoff = INITIAL_RETRY_BACKOFF;
+  let mut attempt = 0u32;
+  let mut route_failures = 0u32;
+  let call_deadline = Instant::now() + STABILITY_PAYMENT_MAX_TOTAL_WAIT;
+
+  loop {
+    attempt += 1;
+
+    if Instant::now() >= call_deadline {
       audit_event(
-        "STABILITY_PAYMENT_FAILED",
+        "STABILITY_PAYMENT_ABANDONED",
         json!({
-            "amount_msats": amt,
-            "error": format!("{e}"),
-            "counterparty": stable_channel.counterparty.to_string()
+            "reason": "total_wait_budget_exhausted",
+            "attempt": attempt
         }),
       );
+      return;
+    }
+
+    if attempt > 1 {
+      if let Ok(price) = crate::oracles::get_latest_price(&agent) {
+        stable_channel.latest_price = price;
+      }
+      let (success, _) = update_balances(node, stable_channel);
+      if !success {
+        audit_event(
+          "STABILITY_PAYMENT_ABANDONED",
+          json!({
+              "reason": "balance_update_failed",
+              "attempt": attempt
+          }),
+        );
+        return;
+      }
+      dollars_from_par = stable_channel.receiver_usd - stable_channel.expected_usd;
+    }
+
+    let percent_from_par = ((dollars_from_par / stable_channel.expected_usd) * 100.0).abs();
+    let amt = USD::to_msats(dollars_from_par, stable_channel.latest_price);
+
+    if percent_from_par < PAR_THRESHOLD_PERCENT || amt < DUST_LIMIT_SATS * 1000 {
+      audit_event(
+        "STABILITY_PAYMENT_ABANDONED",
+        json!({
+            "reason": if percent_from_par < PAR_THRESHOLD_PERCENT { "within_par" } else { "below_dust_limit" },
+            "attempt": attempt,
+            "percent_from_par": percent_from_par,
+            "amount_msats": amt
+        }),
+      );
+      return;
+    }
+
+    let send_result = if let Some(offer) = stable_channel.counterparty_offer.clone() {
+      node
+        .bolt12_payment()
+        .send(&offer, Some(amt), None)
+        .map(|id| (id, "bolt12_offer"))
+    } else {
+      node
+        .spontaneous_payment()
+        .send(amt, stable_channel.counterparty, None)
+        .map(|id| (id, "keysend"))
+    };
+
+    let payment_id = match send_result {
+      Ok((payment_id, method)) => {
+        audit_event(
+          "STABILITY_PAYMENT_DISPATCHED",
+          json!({
+              "amount_msats": amt,
+              "payment_id": payment_id.to_string(),
+              "method": method,
+              "attempt": attempt
+          }),
+        );
+        payment_id
+      }
+      Err(e) => {
+        // send() failed before a payment was even dispatched (e.g. a local
+        // config error), so there is no `PaymentFailureReason` to classify -
+        // that only applies to payments LDK actually attempted to route.
+        audit_event(
+          "STABILITY_PAYMENT_FAILED",
+          json!({
+              "amount_msats": amt,
+              "error": format!("{e}"),
+              "attempt": attempt
+          }),
+        );
+        audit_event(
+          "STABILITY_PAYMENT_ABANDONED",
+          json!({
+              "reason": "send_dispatch_error",
+              "attempt": attempt
+          }),
+        );
+        return;
+      }
+    };
+
+    let outcome_deadline = call_deadline.min(Instant::now() + PAYMENT_OUTCOME_TIMEOUT);
+    match await_payment_outcome(payment_id, outcome_deadline) {
+      PaymentOutcome::Successful => {
+        stable_channel.payment_made = true;
+        audit_event(
+          "STABILITY_PAYMENT_SENT",
+          json!({
+              "amount_msats": amt,
+              "payment_id": payment_id.to_string(),
+              "attempt": attempt
+          }),
+        );
+        return;
+      }
+      PaymentOutcome::Failed(failure_class) => {
+        audit_event(
+          "STABILITY_PAYMENT_FAILED",
+          json!({
+              "amount_msats": amt,
+              "payment_id": payment_id.to_string(),
+              "failure_reason": format!("{:?}", failure_class),
+              "attempt": attempt
+          }),
+        );
+
+        if failure_class != PaymentFailureClass::RouteNotFound {
+          audit_event(
+            "STABILITY_PAYMENT_ABANDONED",
+            json!({
+                "reason": "non_retryable_failure",
+                "failure_reason": format!("{:?}", failure_class),
+                "attempt": attempt
+            }),
+          );
+          return;
+        }
+
+        route_failures += 1;
+        if route_failures >= max_retries {
+          audit_event(
+            "STABILITY_PAYMENT_ABANDONED",
+            json!({
+                "reason": "retry_budget_exhausted",
+                "attempts": attempt
+            }),
+          );
+          return;
+        }
+
+        let remaining = call_deadline.saturating_duration_since(Instant::now());
+        thread::sleep(backoff.min(remaining));
+        backoff = (backoff * 2).min(MAX_RETRY_BACKOFF);
+      }
+      PaymentOutcome::TimedOut => {
+        audit_event(
+          "STABILITY_PAYMENT_ABANDONED",
+          json!({
+              "reason": "timed_out_awaiting_outcome",
+              "payment_id": payment_id.to_string(),
+              "attempt": attempt
+          }),
+        );
+        return;
+      }
     }
   }
 }
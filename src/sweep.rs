@@ -0,0 +1,107 @@
+/* ~~/src/sweep.rs */
+
+use crate::audit::audit_event;
+use ldk_node::lightning::sign::SpendableOutputDescriptor;
+use ldk_node::lightning::util::ser::{Readable, Writeable};
+use ldk_node::Node;
+use serde_json::json;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const PENDING_SWEEPS_FILE: &str = "pending_sweeps.dat";
+
+/// Tracks `SpendableOutputDescriptor`s handed out by closed channels until
+/// they've been swept back to the on-chain wallet. Descriptors are persisted
+/// to disk as they're queued so a restart mid-sweep doesn't strand funds
+/// from channels closed during the `HIGH_RISK_NO_ACTION` path.
+pub struct SweepManager {
+  storage_path: PathBuf,
+  pending: Mutex<Vec<SpendableOutputDescriptor>>,
+}
+
+impl SweepManager {
+  pub fn new(storage_dir: &str) -> Self {
+    let storage_path = Path::new(storage_dir).join(PENDING_SWEEPS_FILE);
+    let pending = Mutex::new(Self::load(&storage_path));
+    Self {
+      storage_path,
+      pending,
+    }
+  }
+
+  fn load(path: &Path) -> Vec<SpendableOutputDescriptor> {
+    let Ok(bytes) = fs::read(path) else {
+      return Vec::new();
+    };
+    let mut cursor = Cursor::new(bytes);
+    let mut descriptors = Vec::new();
+    while let Ok(descriptor) = SpendableOutputDescriptor::read(&mut cursor) {
+      descriptors.push(descriptor);
+    }
+    descriptors
+  }
+
+  fn persist(&self, descriptors: &[SpendableOutputDescriptor]) {
+    let mut bytes = Vec::new();
+    for descriptor in descriptors {
+      let _ = descriptor.write(&mut bytes);
+    }
+    if let Err(e) = fs::write(&self.storage_path, bytes) {
+      audit_event("SWEEP_PERSIST_FAILED", json!({ "error": format!("{e}") }));
+    }
+  }
+
+  /// Queues descriptors surfaced by a `SpendableOutputs` event for the next
+  /// sweep attempt and persists them immediately.
+  pub fn queue(&self, descriptors: Vec<SpendableOutputDescriptor>) {
+    let mut pending = self.pending.lock().unwrap();
+    pending.extend(descriptors);
+    self.persist(&pending);
+  }
+
+  pub fn pending_count(&self) -> usize {
+    self.pending.lock().unwrap().len()
+  }
+
+  /// Builds a transaction sweeping every pending descriptor to a fresh
+  /// on-chain address and broadcasts it, clearing the pending set on
+  /// success.
+  pub fn sweep_now(&self, node: &Node) -> Result<(String, u64), String> {
+    let mut pending = self.pending.lock().unwrap();
+    if pending.is_empty() {
+      return Err("no pending spendable outputs".to_string());
+    }
+
+    let address = node
+      .onchain_payment()
+      .new_address()
+      .map_err(|e| format!("address generation failed: {e}"))?;
+
+    let swept_sats: u64 = pending.iter().map(descriptor_value_sats).sum();
+
+    let txid = node
+      .onchain_payment()
+      .sweep_spendable_outputs(&pending, address)
+      .map_err(|e| format!("sweep broadcast failed: {e}"))?;
+
+    pending.clear();
+    self.persist(&pending);
+
+    audit_event(
+      "SWEEP_BROADCAST",
+      json!({ "txid": txid.to_string(), "swept_sats": swept_sats }),
+    );
+
+    Ok((txid.to_string(), swept_sats))
+  }
+}
+
+fn descriptor_value_sats(descriptor: &SpendableOutputDescriptor) -> u64 {
+  match descriptor {
+    SpendableOutputDescriptor::StaticOutput { output, .. } => output.value.to_sat(),
+    SpendableOutputDescriptor::DelayedPaymentOutput(d) => d.output.value.to_sat(),
+    SpendableOutputDescriptor::StaticPaymentOutput(d) => d.output.value.to_sat(),
+  }
+}
@@ -0,0 +1,90 @@
+/* ~~/src/types.rs */
+
+use ldk_node::bitcoin::secp256k1::PublicKey;
+use ldk_node::lightning::ln::types::ChannelId;
+use ldk_node::lightning::offers::offer::Offer;
+use std::fmt;
+use std::ops::{Div, Sub};
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Bitcoin(pub f64);
+
+impl Bitcoin {
+  pub fn from_sats(sats: u64) -> Self {
+    Self(sats as f64 / 100_000_000.0)
+  }
+}
+
+impl fmt::Display for Bitcoin {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{:.8} BTC", self.0)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct USD(pub f64);
+
+impl USD {
+  pub fn from_bitcoin(btc: Bitcoin, price: f64) -> Self {
+    Self(btc.0 * price)
+  }
+
+  pub fn to_msats(usd: USD, price: f64) -> u64 {
+    if price <= 0.0 {
+      return 0;
+    }
+    ((usd.0 / price) * 100_000_000.0 * 1000.0) as u64
+  }
+}
+
+impl Sub for USD {
+  type Output = USD;
+  fn sub(self, rhs: USD) -> USD {
+    USD(self.0 - rhs.0)
+  }
+}
+
+impl Div for USD {
+  type Output = f64;
+  fn div(self, rhs: USD) -> f64 {
+    self.0 / rhs.0
+  }
+}
+
+impl fmt::Display for USD {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "${:.2}", self.0)
+  }
+}
+
+/// One stable-channel pairing between a receiver and a provider, tracking
+/// the balances and peg state `stable.rs` checks on each poll.
+pub struct StableChannel {
+  pub channel_id: ChannelId,
+  pub is_receiver: bool,
+  pub counterparty: PublicKey,
+  pub expected_usd: USD,
+  pub latest_price: f64,
+  pub risk_level: u32,
+  pub payment_made: bool,
+  pub onchain_btc: Bitcoin,
+  pub onchain_usd: USD,
+  pub receiver_btc: Bitcoin,
+  pub provider_btc: Bitcoin,
+  pub receiver_usd: USD,
+  pub provider_usd: USD,
+
+  /// Standing BOLT12 offer to rebalance against instead of a keysend, when
+  /// present. See `check_stability`'s `PAY` branch.
+  pub counterparty_offer: Option<Offer>,
+
+  /// Max attempts for a stability payment's retry loop; `0` means "use the
+  /// default" (see `stable::DEFAULT_MAX_RETRIES`).
+  pub max_retries: u32,
+
+  /// Whether `check_stability`'s one-time network-graph readiness check has
+  /// already run. Starts `false`; latched `true` the first time that check
+  /// runs, regardless of outcome, so it never re-blocks PAY on small
+  /// networks. See `stable::MIN_GRAPH_CHANNELS`.
+  pub initial_graph_check_passed: bool,
+}